@@ -0,0 +1,46 @@
+use crate::error::Error;
+use crate::{Args, encode_salt, get_salt, output, pool, progress, read_sensitive};
+use argon2::{Argon2, PasswordHasher};
+use std::io::{self, IsTerminal, Write};
+use zeroize::Zeroizing;
+
+/// Hashes every line of stdin as a separate password and writes one encoded hash per line.
+pub fn run(argon2: &Argon2, salt: &[u8], args: &Args) -> Result<(), Error> {
+    // Typed passwords would be echoed, reading them one by one without echo is what the default mode does
+    if io::stdin().is_terminal() {
+        return Err(Error::Params("--batch reads passwords from stdin, pipe them in instead of typing them".to_string()));
+    }
+
+    let input = read_sensitive(io::stdin().lock())
+        .map_err(|e| Error::Io("Error reading input".to_string(), e))?;
+    let passwords = split_lines(&input);
+
+    let spinner = args.progress.then(|| progress::Spinner::start("Hashing"));
+    let hashes = pool::map(&passwords, args.jobs.get(), |password| {
+        // A generated salt has to be fresh for every password
+        let salt = match args.gen_salt {
+            Some(_) => get_salt(args)?,
+            None => salt.to_vec(),
+        };
+        let salt_string = encode_salt(&salt)?;
+        let hash = argon2.hash_password(password, salt_string.as_salt())?;
+        Ok::<_, Error>(Zeroizing::new(hash.to_string()))
+    });
+    drop(spinner);
+
+    let mut out = output::open(args.output.as_deref())?;
+    for hash in hashes {
+        writeln!(out, "{}", hash?.as_str())?;
+    }
+    out.flush()?;
+
+    Ok(())
+}
+
+/// Splits like `str::lines`, but on bytes so passwords need not be valid UTF-8.
+pub fn split_lines(input: &[u8]) -> Vec<&[u8]> {
+    input
+        .split_inclusive(|&byte| byte == b'\n')
+        .map(|line| line.strip_suffix(b"\r\n").or_else(|| line.strip_suffix(b"\n")).unwrap_or(line))
+        .collect()
+}
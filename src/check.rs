@@ -0,0 +1,105 @@
+use crate::batch::split_lines;
+use crate::error::{self, Error};
+use crate::{Args, build_verifier, get_secret, memory, output, pool, progress, read_sensitive, strip_newline, tty};
+use argon2::password_hash::PasswordHash;
+use argon2::PasswordVerifier;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::process::ExitCode;
+use zeroize::Zeroizing;
+
+/// One `<encoded hash> <label>` line of the check file.
+struct Entry<'a> {
+    hash: &'a str,
+    label: String,
+}
+
+/// Verifies a password for every hash in the file, like `sha256sum -c` does for checksums.
+///
+/// Passwords are read from stdin one per line in the order of the file, or prompted for on a
+/// terminal. Exits with 1 if any of them does not match.
+pub fn run(path: &Path, args: &Args) -> Result<ExitCode, Error> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| Error::Io(format!("Cannot read check file {}", path.display()), e))?;
+    let entries = parse(&contents);
+    let passwords = read_passwords(&entries, args)?;
+
+    let secret = get_secret(args)?;
+    let argon2 = build_verifier(secret.as_deref().map(Vec::as_slice))?;
+
+    let work: Vec<(&Entry, &[u8])> = entries.iter()
+        .zip(passwords.iter().map(|password| password.as_slice()))
+        .collect();
+    let limit = memory::limit(args)?;
+    let spinner = args.progress.then(|| progress::Spinner::start("Verifying"));
+    let results = pool::map(&work, args.jobs.get(), |(entry, password)| {
+        let hash = PasswordHash::new(entry.hash)
+            .map_err(|e| Error::Params(format!("Invalid hash: {}", e)))?;
+        memory::check(limit.as_ref(), argon2::Params::try_from(&hash)?.m_cost(), args.jobs.get())?;
+        argon2.verify_password(password, &hash)?;
+        Ok::<_, Error>(())
+    });
+    drop(spinner);
+
+    let mut out = output::open(args.output.as_deref())?;
+    let mut failed = 0;
+    for ((entry, _), result) in work.iter().zip(&results) {
+        match result {
+            Ok(()) if !args.quiet => writeln!(out, "{}: OK", entry.label)?,
+            Ok(()) => {}
+            Err(Error::Mismatch(_)) => writeln!(out, "{}: FAILED", entry.label)?,
+            Err(e) => writeln!(out, "{}: FAILED ({})", entry.label, e)?,
+        }
+        failed += usize::from(result.is_err());
+    }
+    out.flush()?;
+
+    if failed > 0 {
+        eprintln!("{} of {} passwords did not match", failed, entries.len());
+        Ok(ExitCode::from(error::exit::MISMATCH))
+    } else {
+        if !args.quiet {
+            eprintln!("{0} of {0} passwords match", entries.len());
+        }
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+/// Skips blank lines; an entry without a label is named after its line number.
+fn parse(contents: &str) -> Vec<Entry<'_>> {
+    contents.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            let line = line.trim();
+            match line.split_once(char::is_whitespace) {
+                Some((hash, label)) => Entry { hash, label: label.trim_start().to_string() },
+                None => Entry { hash: line, label: format!("line {}", index + 1) },
+            }
+        })
+        .collect()
+}
+
+fn read_passwords(entries: &[Entry], args: &Args) -> Result<Vec<Zeroizing<Vec<u8>>>, Error> {
+    let stdin = io::stdin();
+    let read_error = |e| Error::Io("Error reading input".to_string(), e);
+
+    if stdin.is_terminal() {
+        return entries.iter()
+            .map(|entry| {
+                let input = tty::read_password(&format!("Password for {}: ", entry.label)).map_err(read_error)?;
+                Ok(strip_newline(input, args, true))
+            })
+            .collect();
+    }
+
+    let input = read_sensitive(stdin.lock()).map_err(read_error)?;
+    let lines = split_lines(&input);
+    if lines.len() != entries.len() {
+        return Err(Error::Params(format!(
+            "Expected {} passwords on stdin, one per hash, but got {}", entries.len(), lines.len()
+        )));
+    }
+
+    Ok(lines.into_iter().map(|line| Zeroizing::new(line.to_vec())).collect())
+}
@@ -0,0 +1,122 @@
+use crate::Settings;
+use crate::error::Error;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// The contents of `config.toml`:
+///
+/// ```toml
+/// [defaults]
+/// variant = "argon2id"
+/// memory_kib = 65536
+///
+/// [profiles.web]
+/// memory_kib = 19456
+/// iterations = 2
+/// format = "encoded"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub defaults: Defaults,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Defaults>,
+}
+
+/// Defaults for the parameters, each overridden by the corresponding flag.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Defaults {
+    pub variant: Option<Variant>,
+    pub memory_kib: Option<u32>,
+    pub iterations: Option<u32>,
+    pub parallelism: Option<u32>,
+    pub hash_length: Option<u32>,
+    pub format: Option<Format>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Variant {
+    Argon2i,
+    Argon2d,
+    Argon2id,
+}
+
+/// Output format, matching the default view, `-e` and `-r`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    Full,
+    Encoded,
+    Raw,
+}
+
+impl Config {
+    /// Loads the given file, or the per-user `argon2-cli/config.toml` if it exists.
+    pub fn load(path: Option<&Path>) -> Result<Self, Error> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match default_path() {
+                Some(path) if path.exists() => path,
+                _ => return Ok(Config::default()),
+            },
+        };
+
+        let contents = std::fs::read_to_string(&path)
+            .map_err(|e| Error::Io(format!("Cannot read config file {}", path.display()), e))?;
+        toml::from_str(&contents)
+            .map_err(|e| Error::Params(format!("Invalid config file {}: {}", path.display(), e)))
+    }
+
+    /// The defaults with the given profile layered on top.
+    pub fn select(&self, profile: Option<&str>) -> Result<Defaults, Error> {
+        let Some(name) = profile else {
+            return Ok(self.defaults.clone());
+        };
+        let profile = self.profiles.get(name)
+            .ok_or_else(|| Error::Params(format!("Unknown profile: {}", name)))?;
+
+        Ok(Defaults {
+            variant: profile.variant.or(self.defaults.variant),
+            memory_kib: profile.memory_kib.or(self.defaults.memory_kib),
+            iterations: profile.iterations.or(self.defaults.iterations),
+            parallelism: profile.parallelism.or(self.defaults.parallelism),
+            hash_length: profile.hash_length.or(self.defaults.hash_length),
+            format: profile.format.or(self.defaults.format),
+        })
+    }
+}
+
+impl Defaults {
+    pub fn apply(&self, settings: Settings) -> Settings {
+        Settings {
+            algorithm: match self.variant {
+                Some(Variant::Argon2i) => argon2::Algorithm::Argon2i,
+                Some(Variant::Argon2d) => argon2::Algorithm::Argon2d,
+                Some(Variant::Argon2id) => argon2::Algorithm::Argon2id,
+                None => settings.algorithm,
+            },
+            memory_kib: self.memory_kib.unwrap_or(settings.memory_kib),
+            iterations: self.iterations.unwrap_or(settings.iterations),
+            parallelism: self.parallelism.unwrap_or(settings.parallelism),
+            hash_length: self.hash_length.unwrap_or(settings.hash_length),
+        }
+    }
+}
+
+/// `$XDG_CONFIG_HOME/argon2-cli/config.toml`, falling back to `~/.config`, or `%APPDATA%` on Windows.
+fn default_path() -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+
+    base.map(|dir| dir.join("argon2-cli").join("config.toml"))
+}
@@ -0,0 +1,90 @@
+use argon2::password_hash;
+use std::fmt;
+use std::io;
+use std::process::ExitCode;
+
+/// Exit codes, so that scripts can branch on the outcome.
+///
+/// The reference implementation exits with 1 on any error. Here 1 is kept for
+/// negative answers, like `grep` and `diff` do, and errors get codes of their own.
+pub mod exit {
+    /// A password did not match its hash, or a hash needs to be rehashed
+    pub const MISMATCH: u8 = 1;
+    /// Invalid arguments or parameters, the same code clap uses for usage errors
+    pub const USAGE: u8 = 2;
+    /// The salt is shorter than the 8 bytes Argon2 requires
+    pub const SALT_TOO_SHORT: u8 = 3;
+    /// Reading input or writing output failed
+    pub const IO: u8 = 4;
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// Invalid arguments or Argon2 parameters
+    Params(String),
+    /// The salt is shorter than the 8 bytes Argon2 requires
+    SaltTooShort,
+    /// A password did not match its hash
+    Mismatch(String),
+    /// Reading or writing failed, along with what was being done
+    Io(String, io::Error),
+}
+
+impl Error {
+    pub fn exit_code(&self) -> ExitCode {
+        ExitCode::from(match self {
+            Error::Params(_) => exit::USAGE,
+            Error::SaltTooShort => exit::SALT_TOO_SHORT,
+            Error::Mismatch(_) => exit::MISMATCH,
+            Error::Io(..) => exit::IO,
+        })
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Params(message) | Error::Mismatch(message) => f.write_str(message),
+            Error::SaltTooShort => f.write_str("Salt is too short"),
+            Error::Io(context, e) => write!(f, "{}: {}", context, e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io("Cannot write output".to_string(), e)
+    }
+}
+
+impl From<argon2::Error> for Error {
+    fn from(e: argon2::Error) -> Self {
+        match e {
+            argon2::Error::SaltTooShort => Error::SaltTooShort,
+            e => Error::Params(format!("Invalid parameters: {}", e)),
+        }
+    }
+}
+
+impl From<password_hash::Error> for Error {
+    fn from(e: password_hash::Error) -> Self {
+        match e {
+            password_hash::Error::Password => {
+                Error::Mismatch("The password does not match the supplied hash".to_string())
+            }
+            password_hash::Error::SaltInvalid(password_hash::errors::InvalidValue::TooShort) => {
+                Error::SaltTooShort
+            }
+            e => Error::Params(format!("Invalid parameters: {}", e)),
+        }
+    }
+}
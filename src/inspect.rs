@@ -0,0 +1,93 @@
+use crate::error::Error;
+use argon2::password_hash::PasswordHash;
+use argon2::{Algorithm, Params, Version};
+use serde::Serialize;
+
+/// The parameters an encoded hash was created with.
+#[derive(Debug, Serialize)]
+pub struct Details {
+    pub algorithm: String,
+    pub version: u32,
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+    pub salt: String,
+    pub hash_length: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub associated_data: Option<String>,
+}
+
+/// The Argon2 algorithm, version and parameters embedded in an encoded hash.
+pub struct Encoded {
+    pub algorithm: Algorithm,
+    pub version: Version,
+    pub params: Params,
+}
+
+impl Encoded {
+    pub fn parse(hash: &PasswordHash) -> Result<Self, Error> {
+        let algorithm = Algorithm::try_from(hash.algorithm)
+            .map_err(|e| Error::Params(format!("Unsupported algorithm: {}", e)))?;
+
+        // A missing version field denotes the original 0x10 release
+        let version = match hash.version {
+            Some(version) => Version::try_from(version)
+                .map_err(|e| Error::Params(format!("Unsupported version: {}", e)))?,
+            None => Version::V0x10,
+        };
+
+        let params = Params::try_from(hash)?;
+
+        Ok(Self { algorithm, version, params })
+    }
+}
+
+pub fn details(hash: &str) -> Result<Details, Error> {
+    let hash = PasswordHash::new(hash)
+        .map_err(|e| Error::Params(format!("Invalid encoded hash: {}", e)))?;
+    let encoded = Encoded::parse(&hash)?;
+
+    let mut salt = [0u8; 64];
+    let salt = match &hash.salt {
+        Some(s) => s.decode_b64(&mut salt)
+            .map_err(|e| Error::Params(format!("Invalid salt: {}", e)))?,
+        None => &[],
+    };
+
+    let data = encoded.params.data();
+
+    Ok(Details {
+        algorithm: format!("{:?}", encoded.algorithm),
+        version: encoded.version as u32,
+        memory_kib: encoded.params.m_cost(),
+        iterations: encoded.params.t_cost(),
+        parallelism: encoded.params.p_cost(),
+        salt: hex::encode(salt),
+        hash_length: hash.hash.map_or(0, |h| h.len()),
+        associated_data: (!data.is_empty()).then(|| hex::encode(data)),
+    })
+}
+
+pub fn run(hash: &str, json: bool) -> Result<(), Error> {
+    let details = details(hash)?;
+
+    if json {
+        let json = serde_json::to_string_pretty(&details)
+            .map_err(|e| Error::Io("Cannot encode JSON".to_string(), e.into()))?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    println!("Type:           {}", details.algorithm);
+    println!("Version:        {:x}", details.version);
+    println!("Iterations:     {}", details.iterations);
+    println!("Memory:         {} KiB", details.memory_kib);
+    println!("Parallelism:    {}", details.parallelism);
+    println!("Salt:           {}", details.salt);
+    if let Some(data) = &details.associated_data {
+        println!("Data:           {}", data);
+    }
+    println!("Hash length:    {} bytes", details.hash_length);
+
+    Ok(())
+}
@@ -1,16 +1,72 @@
-use clap::{ArgGroup, Parser};
-use std::io::{self, BufRead, IsTerminal, Write};
+use clap::{ArgGroup, CommandFactory, Parser, Subcommand, ValueEnum};
+use std::io::{self, IsTerminal, Read, Write};
 use argon2::password_hash::SaltString;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use zeroize::Zeroizing;
+
+mod batch;
+mod check;
+mod config;
+mod crack;
+mod error;
+mod inspect;
+mod memory;
+mod measure;
+mod output;
+mod pool;
+mod preset;
+mod progress;
+mod rehash;
+mod selftest;
+mod serve;
+mod tty;
+
+use error::Error;
+use output::Encoding;
+use preset::Preset;
+
+/// Longest salt --gen-salt produces, longer salts only make sense with --kdf.
+const MAX_GEN_SALT_LEN: u64 = 1024;
+
+/// Longest salt an encoded hash can hold, 64 base64 characters.
+const MAX_ENCODED_SALT_LEN: usize = 48;
 
 // Usage:  argon2 [-h] salt [-i|-d|-id] [-t iterations] [-m log2(memory in KiB) | -k memory in KiB] [-p parallelism] [-l hash length] [-e|-r] [-v (10|13)]
 #[derive(Parser, Debug)]
 #[command(name = "argon2", about = "(Rust implementation)", disable_help_flag = false)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+#[command(group(ArgGroup::new("salt_source").args(&["salt", "gen_salt", "salt_file"])))]
+#[command(group(ArgGroup::new("password_source").args(&["password_file", "password_env", "password_fd"])))]
 #[command(group(ArgGroup::new("variant").args(&["i", "d", "id"])))]
 #[command(group(ArgGroup::new("memory").args(&["m", "k"])))]
 #[command(group(ArgGroup::new("output_format").args(&["e", "r"])))]
+#[command(group(ArgGroup::new("secret").args(&["secret_file", "secret_env"])))]
+#[command(group(ArgGroup::new("associated_data").args(&["ad", "ad_file"])))]
+#[command(group(ArgGroup::new("concurrent").args(&["batch", "check"])))]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// The salt to use, at least 8 characters
-    salt: String,
+    #[arg(required_unless_present_any = ["gen_salt", "salt_file", "needs_rehash", "check", "serve"])]
+    salt: Option<String>,
+
+    /// Generate a random salt of N bytes (default 16, at most 1024) instead of passing one
+    #[arg(
+        long, value_name = "N", num_args = 0..=1, default_missing_value = "16",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..=MAX_GEN_SALT_LEN),
+    )]
+    gen_salt: Option<usize>,
+
+    /// Read the salt from a file instead of passing it as argument
+    #[arg(long, value_name = "PATH")]
+    salt_file: Option<PathBuf>,
+
+    /// How the given salt is encoded (default raw)
+    #[arg(long, value_enum, default_value_t = SaltFormat::Raw, conflicts_with = "gen_salt")]
+    salt_format: SaltFormat,
 
     /// Use Argon2i (this is the default)
     #[arg(short = 'i', long, default_value_t = false)]
@@ -25,24 +81,36 @@ struct Args {
     id: bool,
 
     /// Sets the number of iterations to N (default = 3)
-    #[arg(short = 't', default_value_t = 3)]
-    t: u32,
+    #[arg(short = 't')]
+    t: Option<u32>,
 
     /// Sets the memory usage of 2^N KiB (default 12)
-    #[arg(short = 'm', default_value_t = 12)]
-    m: u32,
+    #[arg(short = 'm')]
+    m: Option<u32>,
 
     /// Sets the memory usage of N KiB (default 4096)
     #[arg(short = 'k')]
     k: Option<u32>,
 
     /// Sets parallelism to N threads (default 1)
-    #[arg(short = 'p', default_value_t = 1)]
-    p: u32,
+    #[arg(short = 'p')]
+    p: Option<u32>,
 
     /// Sets hash output length to N bytes (default 32)
-    #[arg(short = 'l', default_value_t = 32)]
-    l: u32,
+    #[arg(short = 'l')]
+    l: Option<u32>,
+
+    /// Start from recommended parameters, individual flags still take precedence
+    #[arg(long, value_enum)]
+    preset: Option<Preset>,
+
+    /// Take defaults from the named profile in the config file
+    #[arg(long, value_name = "NAME")]
+    profile: Option<String>,
+
+    /// Read defaults from this file instead of ~/.config/argon2-cli/config.toml
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
 
     /// Output only encoded hash
     #[arg(short = 'e', default_value_t = false)]
@@ -55,25 +123,501 @@ struct Args {
     /// Argon2 version (defaults to the most recent version, currently 13)
     #[arg(short = 'v', default_value_t = 13)]
     v: u32, // Unimplemented: version selection not supported, always uses v13
+
+    /// Read the secret key (pepper) from the raw bytes of a file
+    #[arg(long, value_name = "PATH")]
+    secret_file: Option<PathBuf>,
+
+    /// Read the secret key (pepper) from an environment variable
+    #[arg(long, value_name = "VAR")]
+    secret_env: Option<String>,
+
+    /// Associated data as a hex string (at most 32 bytes)
+    #[arg(long, value_name = "HEX")]
+    ad: Option<String>,
+
+    /// Read the associated data from the raw bytes of a file
+    #[arg(long, value_name = "PATH")]
+    ad_file: Option<PathBuf>,
+
+    /// Ask for the password twice when reading from a terminal
+    #[arg(long, default_value_t = false, conflicts_with = "password_source")]
+    confirm: bool,
+
+    /// Keep a trailing line break in the password, whatever the source
+    #[arg(long, default_value_t = false, conflicts_with_all = ["strip_newline", "batch"])]
+    keep_newline: bool,
+
+    /// Drop a single trailing line break from the password, also when piped into stdin
+    #[arg(long, default_value_t = false, conflicts_with = "batch")]
+    strip_newline: bool,
+
+    /// Read the password from a file instead of stdin
+    #[arg(long, value_name = "PATH")]
+    password_file: Option<PathBuf>,
+
+    /// Read the password from an environment variable instead of stdin
+    #[arg(long, value_name = "VAR")]
+    password_env: Option<String>,
+
+    /// Read the password from an open file descriptor instead of stdin (Unix only)
+    #[arg(long, value_name = "N")]
+    password_fd: Option<i32>,
+
+    /// Check whether an encoded hash meets the selected parameters; exits with 1 if it should be rehashed
+    #[arg(long, value_name = "HASH", conflicts_with_all = ["salt_source", "password_source", "output_format", "kdf"])]
+    needs_rehash: Option<String>,
+
+    /// Verify passwords from stdin against a file of "<encoded hash> <label>" lines; exits with 1 if any fails
+    #[arg(long, value_name = "PATH", conflicts_with_all = [
+        "salt_source", "password_source", "confirm", "output_format", "kdf", "needs_rehash", "batch", "measure", "json",
+    ])]
+    check: Option<PathBuf>,
+
+    /// Answer JSON hash and verify requests read line by line from stdin until it is closed
+    #[arg(long, default_value_t = false, conflicts_with_all = [
+        "salt_source", "password_source", "confirm", "output_format", "kdf", "needs_rehash", "batch", "check",
+        "measure", "json", "output",
+    ])]
+    serve: bool,
+
+    /// Show a spinner with the elapsed time on stderr while hashing
+    #[arg(long, default_value_t = false)]
+    progress: bool,
+
+    /// Refuse parameters that need more memory than the detected limit (auto) or the given KiB
+    #[arg(long, value_name = "auto|KiB")]
+    max_memory: Option<memory::MaxMemory>,
+
+    /// Hash every line of stdin as a separate password and print one encoded hash per line
+    #[arg(long, default_value_t = false, conflicts_with_all = ["password_source", "confirm", "r", "kdf", "needs_rehash"])]
+    batch: bool,
+
+    /// Number of hashes computed at the same time in batch and check mode, independent of -p
+    #[arg(long, value_name = "N", default_value = "1", requires = "concurrent")]
+    jobs: NonZeroUsize,
+
+    /// Derive a key of -l bytes instead of a password hash, skipping the encoded output
+    #[arg(long, default_value_t = false, conflicts_with = "output_format")]
+    kdf: bool,
+
+    /// Write the hash or derived key to a file only readable by the current user instead of stdout
+    #[arg(short = 'o', long, value_name = "PATH")]
+    output: Option<PathBuf>,
+
+    /// Only print the hash, without the parameters and timing
+    #[arg(short = 'q', long, default_value_t = false)]
+    quiet: bool,
+
+    /// Hash N times and report the minimum, average, maximum and standard deviation of the time taken
+    #[arg(long, value_name = "N", conflicts_with_all = ["output_format", "quiet", "batch", "kdf", "needs_rehash"])]
+    measure: Option<NonZeroUsize>,
+
+    /// Print the parameters, hash and timing as JSON
+    #[arg(long, default_value_t = false, conflicts_with_all = ["output_format", "batch", "kdf", "needs_rehash"])]
+    json: bool,
+
+    /// Encoding of the hash or derived key (default hex, or raw with -r and --kdf)
+    #[arg(long, value_enum, conflicts_with_all = ["e", "batch"])]
+    raw_encoding: Option<Encoding>,
+
+    /// Check the Argon2 implementation against known-answer test vectors for every variant and version
+    #[arg(long, default_value_t = false, exclusive = true)]
+    self_test: bool,
+
+    /// Print completions for the given shell
+    #[arg(long, value_name = "SHELL", value_enum, hide = true, exclusive = true)]
+    generate_completions: Option<clap_complete::Shell>,
+
+    /// Print a man page in roff format
+    #[arg(long, default_value_t = false, hide = true, exclusive = true)]
+    generate_man: bool,
 }
 
-fn get_input() -> io::Result<String> {
+/// The hashing parameters selected on the command line.
+#[derive(Clone, Copy)]
+struct Settings {
+    algorithm: argon2::Algorithm,
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+    hash_length: u32,
+}
+
+impl Settings {
+    /// The defaults of the reference implementation.
+    const DEFAULT: Settings = Settings {
+        algorithm: argon2::Algorithm::Argon2i,
+        memory_kib: 1 << 12,
+        iterations: 3,
+        parallelism: 1,
+        hash_length: 32,
+    };
+}
+
+fn settings(args: &Args, defaults: &config::Defaults) -> Result<Settings, Error> {
+    let base = match args.preset {
+        Some(preset) => preset.settings(),
+        None => defaults.apply(Settings::DEFAULT),
+    };
+
+    // Select algorithm variant
+    let algorithm = if args.i {
+        argon2::Algorithm::Argon2i
+    } else if args.d {
+        argon2::Algorithm::Argon2d
+    } else if args.id {
+        argon2::Algorithm::Argon2id
+    } else {
+        base.algorithm
+    };
+
+    // Calculate memory cost
+    let memory_kib = if let Some(k) = args.k {
+        k
+    } else if let Some(m) = args.m {
+        1u32.checked_shl(m)
+            .ok_or_else(|| Error::Params(format!("Memory cost 2^{} KiB is too large", m)))?
+    } else {
+        base.memory_kib
+    };
+
+    Ok(Settings {
+        algorithm,
+        memory_kib,
+        iterations: args.t.unwrap_or(base.iterations),
+        parallelism: args.p.unwrap_or(base.parallelism),
+        hash_length: args.l.unwrap_or(base.hash_length),
+    })
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Decode an encoded hash and print the parameters it was created with
+    Inspect {
+        /// The encoded hash, e.g. '$argon2id$v=19$m=65536,t=3,p=4$...'
+        hash: String,
+
+        /// Print the parameters as JSON
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+    /// Try every line of a wordlist as the password of an encoded hash and print the match
+    Crack {
+        /// The encoded hash, e.g. '$argon2id$v=19$m=65536,t=3,p=4$...'
+        #[arg(long)]
+        hash: String,
+
+        /// File with one candidate password per line
+        #[arg(long, value_name = "PATH")]
+        wordlist: PathBuf,
+
+        /// Number of candidates tried at the same time
+        #[arg(long, value_name = "N", default_value = "1")]
+        jobs: NonZeroUsize,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum SaltFormat {
+    /// Use the bytes as given
+    Raw,
+    /// Hex encoded bytes
+    Hex,
+    /// Base64 encoded bytes, with or without padding
+    B64,
+}
+
+/// Reads everything into a buffer that is wiped when dropped.
+///
+/// `read_to_end` would grow the vector by reallocating and free the old copies as they are, so
+/// every step copies into a new buffer by hand and lets the old one be wiped.
+fn read_sensitive(mut reader: impl Read) -> io::Result<Zeroizing<Vec<u8>>> {
+    let mut input = Zeroizing::new(Vec::with_capacity(1024));
+    loop {
+        if input.len() == input.capacity() {
+            let mut grown = Zeroizing::new(Vec::with_capacity(input.capacity() * 2));
+            grown.extend_from_slice(&input);
+            input = grown;
+        }
+
+        // Stays within the capacity, so resizing never reallocates
+        let len = input.len();
+        let capacity = input.capacity();
+        input.resize(capacity, 0);
+        match reader.read(&mut input[len..]) {
+            Ok(0) => {
+                input.truncate(len);
+                return Ok(input);
+            }
+            Ok(read) => input.truncate(len + read),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => input.truncate(len),
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Opens and reads a file with [`read_sensitive`].
+fn read_sensitive_file(path: &std::path::Path) -> io::Result<Zeroizing<Vec<u8>>> {
+    read_sensitive(std::fs::File::open(path)?)
+}
+
+fn get_input(args: &Args) -> Result<Zeroizing<Vec<u8>>, Error> {
     let stdin = io::stdin();
+    let read_error = |e| Error::Io("Error reading input".to_string(), e);
 
     if stdin.is_terminal() {
-        print!("Enter password: ");
-        io::stdout().flush()?;
+        let input = tty::read_password("Enter password: ").map_err(read_error)?;
+        if args.confirm && tty::read_password("Confirm password: ").map_err(read_error)?.as_slice() != input.as_slice() {
+            return Err(Error::Mismatch("Passwords do not match".to_string()));
+        }
+        Ok(strip_newline(input, args, true))
+    } else {
+        // Piped input is hashed byte for byte, like the reference implementation does
+        let input = read_sensitive(stdin.lock()).map_err(read_error)?;
+        Ok(strip_newline(input, args, false))
+    }
+}
+
+/// Drops a single trailing line break, as left behind by `echo` or an editor.
+///
+/// `strip` is the default for the password source, `--keep-newline` and `--strip-newline` override it.
+fn strip_newline(mut input: Zeroizing<Vec<u8>>, args: &Args, strip: bool) -> Zeroizing<Vec<u8>> {
+    if args.keep_newline || !(strip || args.strip_newline) {
+        return input;
+    }
+    if input.ends_with(b"\n") {
+        input.pop();
+        if input.ends_with(b"\r") {
+            input.pop();
+        }
+    }
+    input
+}
+
+#[cfg(unix)]
+fn read_fd(fd: i32) -> Result<Zeroizing<Vec<u8>>, Error> {
+    use std::os::fd::BorrowedFd;
 
-        let mut input = String::new();
-        stdin.read_line(&mut input)?;
-        Ok(input.trim().to_string())
+    // The descriptor is only borrowed, so it has to be open and stays owned by whoever opened it
+    if fd < 0 || unsafe { libc::fcntl(fd, libc::F_GETFD) } == -1 {
+        return Err(Error::Params(format!("File descriptor {} is not open", fd)));
+    }
+    let fd_error = |e| Error::Io(format!("Cannot read password from fd {}", fd), e);
+    let borrowed = unsafe { BorrowedFd::borrow_raw(fd) };
+    let file = std::fs::File::from(borrowed.try_clone_to_owned().map_err(fd_error)?);
+
+    read_sensitive(file).map_err(fd_error)
+}
+
+#[cfg(not(unix))]
+fn read_fd(_fd: i32) -> Result<Zeroizing<Vec<u8>>, Error> {
+    Err(Error::Params("--password-fd is only supported on Unix".to_string()))
+}
+
+/// Reads an environment variable as bytes, which on Unix need not be valid UTF-8.
+fn read_env(var: &str) -> Result<Zeroizing<Vec<u8>>, std::env::VarError> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStringExt;
+
+        std::env::var_os(var)
+            .map(|value| Zeroizing::new(value.into_vec()))
+            .ok_or(std::env::VarError::NotPresent)
+    }
+
+    #[cfg(not(unix))]
+    std::env::var(var).map(|value| Zeroizing::new(value.into_bytes()))
+}
+
+fn get_password(args: &Args) -> Result<Zeroizing<Vec<u8>>, Error> {
+    if let Some(path) = &args.password_file {
+        let input = read_sensitive_file(path)
+            .map_err(|e| Error::Io(format!("Cannot read password file {}", path.display()), e))?;
+        Ok(strip_newline(input, args, true))
+    } else if let Some(var) = &args.password_env {
+        let input = read_env(var)
+            .map_err(|e| Error::Params(format!("Cannot read password from ${}: {}", var, e)))?;
+        Ok(strip_newline(input, args, false))
+    } else if let Some(fd) = args.password_fd {
+        let input = read_fd(fd)?;
+        Ok(strip_newline(input, args, true))
     } else {
-        let lines: Vec<String> = stdin.lock().lines().collect::<Result<_, _>>()?;
-        Ok(lines.join("\n"))
+        get_input(args)
     }
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+fn get_secret(args: &Args) -> Result<Option<Zeroizing<Vec<u8>>>, Error> {
+    if let Some(path) = &args.secret_file {
+        let secret = read_sensitive_file(path)
+            .map_err(|e| Error::Io(format!("Cannot read secret file {}", path.display()), e))?;
+        Ok(Some(secret))
+    } else if let Some(var) = &args.secret_env {
+        let secret = read_env(var)
+            .map_err(|e| Error::Params(format!("Cannot read secret from ${}: {}", var, e)))?;
+        Ok(Some(secret))
+    } else {
+        Ok(None)
+    }
+}
+
+fn get_associated_data(args: &Args) -> Result<Option<Vec<u8>>, Error> {
+    if let Some(ad) = &args.ad {
+        let ad = hex::decode(ad)
+            .map_err(|e| Error::Params(format!("Invalid associated data: {}", e)))?;
+        Ok(Some(ad))
+    } else if let Some(path) = &args.ad_file {
+        let ad = std::fs::read(path)
+            .map_err(|e| Error::Io(format!("Cannot read associated data file {}", path.display()), e))?;
+        Ok(Some(ad))
+    } else {
+        Ok(None)
+    }
+}
+
+fn get_salt(args: &Args) -> Result<Vec<u8>, Error> {
+    if let Some(len) = args.gen_salt {
+        let mut salt = vec![0u8; len];
+        getrandom::fill(&mut salt)
+            .map_err(|e| Error::Io("Cannot generate salt".to_string(), e.into()))?;
+        return check_salt(salt);
+    }
+
+    let salt = if let Some(path) = &args.salt_file {
+        std::fs::read(path)
+            .map_err(|e| Error::Io(format!("Cannot read salt file {}", path.display()), e))?
+    } else {
+        args.salt.as_deref().unwrap_or_default().as_bytes().to_vec()
+    };
+
+    let salt = match args.salt_format {
+        SaltFormat::Raw => salt,
+        SaltFormat::Hex => hex::decode(salt.trim_ascii())
+            .map_err(|e| Error::Params(format!("Invalid hex salt: {}", e)))?,
+        SaltFormat::B64 => {
+            use base64ct::{Base64, Base64Unpadded, Encoding};
+            let salt = std::str::from_utf8(salt.trim_ascii())
+                .map_err(|e| Error::Params(format!("Invalid base64 salt: {}", e)))?;
+            Base64::decode_vec(salt)
+                .or_else(|_| Base64Unpadded::decode_vec(salt))
+                .map_err(|e| Error::Params(format!("Invalid base64 salt: {}", e)))?
+        }
+    };
+
+    check_salt(salt)
+}
+
+fn check_salt(salt: Vec<u8>) -> Result<Vec<u8>, Error> {
+    if salt.len() < argon2::MIN_SALT_LEN {
+        return Err(Error::SaltTooShort);
+    }
+    Ok(salt)
+}
+
+/// Encodes the salt for a PHC string, which has no room for more than 48 bytes.
+fn encode_salt(salt: &[u8]) -> Result<SaltString, Error> {
+    if salt.len() > MAX_ENCODED_SALT_LEN {
+        return Err(Error::Params(format!(
+            "Salt of {} bytes is too long for an encoded hash, which holds at most {} bytes",
+            salt.len(), MAX_ENCODED_SALT_LEN
+        )));
+    }
+    SaltString::encode_b64(salt).map_err(|e| Error::Params(format!("Invalid salt: {}", e)))
+}
+
+/// Sets up Argon2 v0x13 with the selected parameters, pepper and associated data.
+fn build_argon2<'k>(
+    settings: &Settings,
+    secret: Option<&'k [u8]>,
+    associated_data: Option<&[u8]>,
+) -> Result<argon2::Argon2<'k>, Error> {
+    let mut params = argon2::ParamsBuilder::new();
+    params
+        .m_cost(settings.memory_kib)
+        .t_cost(settings.iterations)
+        .p_cost(settings.parallelism)
+        .output_len(settings.hash_length as usize);
+
+    if let Some(ad) = associated_data {
+        let ad = argon2::AssociatedData::new(ad)
+            .map_err(|e| Error::Params(format!("Invalid associated data: {}", e)))?;
+        params.data(ad);
+    }
+
+    let params = params.build()?;
+
+    Ok(match secret {
+        Some(secret) => argon2::Argon2::new_with_secret(
+            secret,
+            settings.algorithm,
+            argon2::Version::V0x13,
+            params,
+        )?,
+        None => argon2::Argon2::new(
+            settings.algorithm,
+            argon2::Version::V0x13,
+            params,
+        ),
+    })
+}
+
+/// Sets up Argon2 for verifying encoded hashes, which carry their own parameters, so only the
+/// pepper has to be supplied.
+fn build_verifier(secret: Option<&[u8]>) -> Result<argon2::Argon2<'_>, Error> {
+    Ok(match secret {
+        Some(secret) => argon2::Argon2::new_with_secret(
+            secret,
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        )?,
+        None => argon2::Argon2::default(),
+    })
+}
+
+fn derive_key(
+    argon2: &argon2::Argon2,
+    password: &[u8],
+    salt: &[u8],
+    key_length: u32,
+    args: &Args,
+) -> Result<(), Error> {
+    let mut key = Zeroizing::new(vec![0u8; key_length as usize]);
+    {
+        let _spinner = args.progress.then(|| progress::Spinner::start("Deriving key"));
+        argon2.hash_password_into(password, salt, &mut key)?;
+    }
+
+    let encoding = args.raw_encoding.unwrap_or(Encoding::Raw);
+    let encoded = encoding.encode(&key);
+
+    let mut out = output::open(args.output.as_deref())?;
+    out.write_all(&encoded)?;
+    // Files receive exactly the encoded key
+    if encoding.is_text() && args.output.is_none() {
+        writeln!(out)?;
+    }
+    out.flush()?;
+
+    // The key alone cannot be reproduced without a generated salt
+    if args.gen_salt.is_some() {
+        eprintln!("Salt:           {}", hex::encode(salt));
+    }
+
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            e.exit_code()
+        }
+    }
+}
+
+fn run() -> Result<ExitCode, Error> {
     // Handle the non-standard `-id` flag which conflicts with clap's short flag clustering
     let args_env = std::env::args();
     let new_args: Vec<String> = args_env.map(|arg| {
@@ -84,77 +628,181 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }).collect();
 
-    let args = Args::parse_from(new_args);
+    let mut args = Args::parse_from(new_args);
 
-    let password = get_input().unwrap_or_else(|e| {
-        eprintln!("Error reading input: {}", e);
-        std::process::exit(1);
-    });
-    
-    // Select algorithm variant
-    let algorithm = if args.d {
-        argon2::Algorithm::Argon2d
-    } else if args.id {
-        argon2::Algorithm::Argon2id
-    } else {
-        argon2::Algorithm::Argon2i
-    };
+    // Both are generated for the installed binary rather than the reference `argon2` name
+    if let Some(shell) = args.generate_completions {
+        let mut command = Args::command();
+        clap_complete::generate(shell, &mut command, env!("CARGO_BIN_NAME"), &mut io::stdout());
+        return Ok(ExitCode::SUCCESS);
+    }
 
-    // Calculate memory cost
-    let memory_kib = if let Some(k) = args.k {
-        k
-    } else {
-        1 << args.m
-    };
+    if args.self_test {
+        return selftest::run();
+    }
 
-    let params = argon2::Params::new(
-        memory_kib,
-        args.t,
-        args.p,
-        Some(args.l as usize),
-    ).map_err(|e| format!("Invalid parameters: {}", e))?;
+    if args.generate_man {
+        let command = Args::command().name(env!("CARGO_BIN_NAME"));
+        clap_mangen::Man::new(command).render(&mut io::stdout())?;
+        return Ok(ExitCode::SUCCESS);
+    }
 
-    // Encode salt to PHC string format
-    let salt_string = SaltString::encode_b64(args.salt.as_bytes())
-        .map_err(|e| format!("Invalid salt: {}", e))?;
+    match &args.command {
+        Some(Command::Inspect { hash, json }) => {
+            inspect::run(hash, *json)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Crack { hash, wordlist, jobs }) => return crack::run(hash, wordlist, jobs.get()),
+        None => {}
+    }
 
-    let argon2 = argon2::Argon2::new(
-        algorithm,
-        argon2::Version::V0x13,
-        params,
-    );
+    if let Some(path) = &args.check {
+        return check::run(path, &args);
+    }
+
+    if args.json && args.raw_encoding == Some(Encoding::Raw) {
+        return Err(Error::Params("JSON output cannot hold raw bytes, use hex, b64 or b64url".to_string()));
+    }
+    if !args.r && !args.kdf && args.raw_encoding == Some(Encoding::Raw) {
+        return Err(Error::Params("The text output cannot hold raw bytes, use hex, b64 or b64url, or -r".to_string()));
+    }
+
+    let defaults = config::Config::load(args.config.as_deref())?
+        .select(args.profile.as_deref())?;
+
+    // A configured output format only applies when no output mode was chosen on the command line,
+    // setting -e or -r here would otherwise bypass clap's conflict checks
+    if !args.e && !args.r && !args.json && args.raw_encoding.is_none() && args.measure.is_none() {
+        match defaults.format {
+            Some(config::Format::Encoded) => args.e = true,
+            Some(config::Format::Raw) => args.r = true,
+            Some(config::Format::Full) | None => {}
+        }
+    }
+
+    let settings = settings(&args, &defaults)?;
+
+    if let Some(hash) = &args.needs_rehash {
+        return match rehash::run(hash, &settings)? {
+            true => Ok(ExitCode::SUCCESS),
+            false => Ok(ExitCode::from(error::exit::MISMATCH)),
+        };
+    }
+
+    if args.serve {
+        serve::run(&settings, &args)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // Batch mode holds the memory for up to --jobs hashes at the same time
+    let concurrent = if args.batch { args.jobs.get() } else { 1 };
+    memory::check(memory::limit(&args)?.as_ref(), settings.memory_kib, concurrent)?;
+
+    let secret = get_secret(&args)?;
+    let associated_data = get_associated_data(&args)?;
+    let salt = get_salt(&args)?;
+
+    let argon2 = build_argon2(&settings, secret.as_deref().map(Vec::as_slice), associated_data.as_deref())?;
+
+    if args.batch {
+        batch::run(&argon2, &salt, &args)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let password = get_password(&args)?;
+
+    if args.kdf {
+        derive_key(&argon2, &password, &salt, settings.hash_length, &args)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // Encode salt to PHC string format, only the encoded hash has a length limit for it
+    let salt_string = encode_salt(&salt)?;
+    let password_bytes = password.as_slice();
+
+    let spinner = args.progress.then(|| progress::Spinner::start("Hashing"));
 
-    let start = std::time::Instant::now();
-    
-    let password_bytes = password.as_bytes();
-    
     use argon2::PasswordHasher;
-    let password_hash = argon2.hash_password(password_bytes, salt_string.as_salt())
-        .map_err(|e| format!("Hashing failed: {}", e))?;
-    
-    let duration = start.elapsed();
+    let start = std::time::Instant::now();
+    let password_hash = argon2.hash_password(password_bytes, salt_string.as_salt())?;
+    let mut durations = vec![start.elapsed()];
+
+    // Repeat the same hash for --measure, the result is identical every time
+    for _ in 1..args.measure.map_or(1, NonZeroUsize::get) {
+        let start = std::time::Instant::now();
+        argon2.hash_password(password_bytes, salt_string.as_salt())?;
+        durations.push(start.elapsed());
+    }
+    let timing = measure::Timing::new(&durations);
+
+    // Check the encoded hash against the same instance, so a pepper is honoured here too
+    use argon2::PasswordVerifier;
+    argon2.verify_password(password_bytes, &password_hash)?;
+    drop(spinner);
+
+    let mut out = output::open(args.output.as_deref())?;
+    let encoding = args.raw_encoding.unwrap_or(Encoding::Hex);
 
     // Generate output based on flags
     if args.e {
-        println!("{}", password_hash);
+        writeln!(out, "{}", password_hash)?;
     } else if args.r {
+        // The raw bytes alone cannot be reproduced without a generated salt
+        if args.gen_salt.is_some() {
+            eprintln!("Salt:           {}", hex::encode(&salt));
+        }
         if let Some(hash) = password_hash.hash {
-             io::stdout().write_all(hash.as_bytes())?;
+            let encoding = args.raw_encoding.unwrap_or(Encoding::Raw);
+            out.write_all(&encoding.encode(hash.as_bytes()))?;
+            // Files receive exactly the encoded hash
+            if encoding.is_text() && args.output.is_none() {
+                writeln!(out)?;
+            }
         }
+    } else if args.json {
+        let report = measure::Report {
+            algorithm: format!("{:?}", settings.algorithm),
+            version: u32::from(argon2::Version::V0x13),
+            memory_kib: settings.memory_kib,
+            iterations: settings.iterations,
+            parallelism: settings.parallelism,
+            salt: args.gen_salt.map(|_| hex::encode(&salt)),
+            hash: password_hash.hash
+                .map(|hash| String::from_utf8_lossy(&encoding.encode(hash.as_bytes())).into_owned())
+                .unwrap_or_default(),
+            encoded: password_hash.to_string(),
+            timing,
+        };
+        let json = serde_json::to_string_pretty(&report)
+            .map_err(|e| Error::Io("Cannot encode JSON".to_string(), e.into()))?;
+        writeln!(out, "{}", Zeroizing::new(json).as_str())?;
     } else {
-        println!("Type:           {:?}", algorithm);
-        println!("Iterations:     {}", args.t);
-        println!("Memory:         {} KiB", memory_kib);
-        println!("Parallelism:    {}", args.p);
+        if !args.quiet {
+            writeln!(out, "Type:           {:?}", settings.algorithm)?;
+            writeln!(out, "Iterations:     {}", settings.iterations)?;
+            writeln!(out, "Memory:         {} KiB", settings.memory_kib)?;
+            writeln!(out, "Parallelism:    {}", settings.parallelism)?;
+        }
+        if args.gen_salt.is_some() {
+            writeln!(out, "Salt:           {}", hex::encode(&salt))?;
+        }
         
         if let Some(hash) = password_hash.hash {
-            println!("Hash:           {}", hex::encode(hash.as_bytes()));
+            write!(out, "Hash:           ")?;
+            out.write_all(&encoding.encode(hash.as_bytes()))?;
+            writeln!(out)?;
         }
-        println!("Encoded:        {}", password_hash);
+        writeln!(out, "Encoded:        {}", password_hash)?;
         
-        println!("{:.3} seconds", duration.as_secs_f64());
-        println!("Verification ok");
+        if !args.quiet {
+            match args.measure {
+                Some(_) => writeln!(out, "{}", timing)?,
+                None => writeln!(out, "{:.3} seconds", timing.avg)?,
+            }
+            writeln!(out, "Verification ok")?;
+        }
     }
+    out.flush()?;
 
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
@@ -0,0 +1,71 @@
+use serde::Serialize;
+use std::fmt;
+use std::time::Duration;
+use zeroize::Zeroize;
+
+/// Wall time statistics over repeated runs, in seconds.
+#[derive(Debug, Serialize)]
+pub struct Timing {
+    pub runs: usize,
+    pub min: f64,
+    pub avg: f64,
+    pub max: f64,
+    pub stddev: f64,
+}
+
+impl Timing {
+    pub fn new(durations: &[Duration]) -> Self {
+        let seconds: Vec<f64> = durations.iter().map(Duration::as_secs_f64).collect();
+        let runs = seconds.len();
+        let avg = seconds.iter().sum::<f64>() / runs as f64;
+
+        // Sample standard deviation, a single run has none
+        let stddev = if runs > 1 {
+            let variance = seconds.iter().map(|s| (s - avg).powi(2)).sum::<f64>() / (runs - 1) as f64;
+            variance.sqrt()
+        } else {
+            0.0
+        };
+
+        Timing {
+            runs,
+            min: seconds.iter().copied().fold(f64::INFINITY, f64::min),
+            avg,
+            max: seconds.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            stddev,
+        }
+    }
+}
+
+impl fmt::Display for Timing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.3} seconds min, {:.3} avg, {:.3} max, {:.3} stddev over {} runs",
+            self.min, self.avg, self.max, self.stddev, self.runs
+        )
+    }
+}
+
+/// The default view as JSON, printed with `--json`.
+#[derive(Serialize)]
+pub struct Report {
+    pub algorithm: String,
+    pub version: u32,
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub salt: Option<String>,
+    pub hash: String,
+    pub encoded: String,
+    pub timing: Timing,
+}
+
+/// The encoded hash holds the same bytes as `hash` in base64, so both are wiped.
+impl Drop for Report {
+    fn drop(&mut self) {
+        self.hash.zeroize();
+        self.encoded.zeroize();
+    }
+}
@@ -0,0 +1,120 @@
+use crate::Args;
+use crate::error::Error;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// The value of `--max-memory`: `auto` or a limit in KiB.
+#[derive(Clone, Copy, Debug)]
+pub enum MaxMemory {
+    Auto,
+    KiB(u64),
+}
+
+impl FromStr for MaxMemory {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(MaxMemory::Auto),
+            _ => s.parse().map(MaxMemory::KiB)
+                .map_err(|_| format!("expected `auto` or a size in KiB, got `{}`", s)),
+        }
+    }
+}
+
+/// The memory available to the process and where that number came from.
+#[derive(Debug)]
+pub struct Limit {
+    pub kib: u64,
+    pub source: &'static str,
+}
+
+/// The limit selected with `--max-memory`, if any.
+///
+/// Fails when `auto` finds nothing to go by, as on systems without /proc, rather than silently
+/// skipping the check that was asked for.
+pub fn limit(args: &Args) -> Result<Option<Limit>, Error> {
+    match args.max_memory {
+        None => Ok(None),
+        Some(MaxMemory::Auto) => detect().map(Some).ok_or_else(|| {
+            Error::Params("Cannot detect the available memory, pass --max-memory a limit in KiB instead".to_string())
+        }),
+        Some(MaxMemory::KiB(kib)) => Ok(Some(Limit { kib, source: "--max-memory" })),
+    }
+}
+
+/// Refuses parameters that need more memory than the limit, before the kernel kills the process.
+pub fn check(limit: Option<&Limit>, memory_kib: u32, concurrent: usize) -> Result<(), Error> {
+    let Some(limit) = limit else {
+        return Ok(());
+    };
+
+    let needed = u64::from(memory_kib) * concurrent as u64;
+    if needed > limit.kib {
+        let hashes = match concurrent {
+            1 => String::new(),
+            n => format!(" for {} concurrent hashes", n),
+        };
+        return Err(Error::Params(format!(
+            "{} KiB of memory needed{} exceeds the limit of {} KiB from {}",
+            needed, hashes, limit.kib, limit.source
+        )));
+    }
+    Ok(())
+}
+
+/// The lowest of the cgroup v2 and v1 limits and the memory currently available.
+fn detect() -> Option<Limit> {
+    let cgroups = std::fs::read_to_string("/proc/self/cgroup").unwrap_or_default();
+    let mut limits = Vec::new();
+
+    // Lines are `<id>:<controllers>:<path>`, the unified hierarchy has id 0 and no controllers
+    for line in cgroups.lines() {
+        let mut fields = line.splitn(3, ':');
+        let (Some(id), Some(controllers), Some(path)) = (fields.next(), fields.next(), fields.next()) else {
+            continue;
+        };
+
+        let limit = if id == "0" && controllers.is_empty() {
+            cgroup_limit("/sys/fs/cgroup", path, "memory.max")
+                .map(|kib| Limit { kib, source: "cgroup memory.max" })
+        } else if controllers.split(',').any(|controller| controller == "memory") {
+            cgroup_limit("/sys/fs/cgroup/memory", path, "memory.limit_in_bytes")
+                .map(|kib| Limit { kib, source: "cgroup memory.limit_in_bytes" })
+        } else {
+            None
+        };
+        limits.extend(limit);
+    }
+
+    limits.extend(mem_available().map(|kib| Limit { kib, source: "MemAvailable in /proc/meminfo" }));
+    limits.into_iter().min_by_key(|limit| limit.kib)
+}
+
+/// The lowest limit in KiB set on the cgroup at `path` below `mount` or any of its parents.
+fn cgroup_limit(mount: &str, path: &str, file: &str) -> Option<u64> {
+    let read = |dir: &PathBuf| -> Option<u64> {
+        let bytes: u64 = std::fs::read_to_string(dir.join(file)).ok()?.trim().parse().ok()?;
+        // v2 writes `max` when unlimited, which fails to parse; v1 writes a value close to i64::MAX
+        (bytes < 1 << 62).then_some(bytes / 1024)
+    };
+
+    let mut dir = PathBuf::from(mount);
+    let mut lowest = read(&dir);
+    for component in path.split('/').filter(|component| !component.is_empty()) {
+        dir.push(component);
+        lowest = [lowest, read(&dir)].into_iter().flatten().min();
+    }
+    lowest
+}
+
+fn mem_available() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    meminfo.lines()
+        .find_map(|line| line.strip_prefix("MemAvailable:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse()
+        .ok()
+}
@@ -0,0 +1,70 @@
+use crate::error::Error;
+use clap::ValueEnum;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use zeroize::Zeroizing;
+
+/// How raw hash or key bytes are written out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Encoding {
+    /// The bytes as they are
+    Raw,
+    /// Lowercase hex
+    Hex,
+    /// Standard base64 with padding
+    B64,
+    /// URL-safe base64 without padding
+    #[value(name = "b64url")]
+    B64Url,
+}
+
+impl Encoding {
+    pub fn encode(self, bytes: &[u8]) -> Zeroizing<Vec<u8>> {
+        use base64ct::{Base64, Base64UrlUnpadded, Encoding as _};
+
+        Zeroizing::new(match self {
+            Encoding::Raw => bytes.to_vec(),
+            Encoding::Hex => hex::encode(bytes).into_bytes(),
+            Encoding::B64 => Base64::encode_string(bytes).into_bytes(),
+            Encoding::B64Url => Base64UrlUnpadded::encode_string(bytes).into_bytes(),
+        })
+    }
+
+    /// Whether the encoded bytes are printable text, which gets a trailing newline on stdout.
+    pub fn is_text(self) -> bool {
+        self != Encoding::Raw
+    }
+}
+
+/// Opens the destination for results: the file given with `-o`, or stdout.
+pub fn open(path: Option<&Path>) -> Result<Box<dyn Write>, Error> {
+    match path {
+        Some(path) => {
+            let file = create_private_file(path)
+                .map_err(|e| Error::Io(format!("Cannot create {}", path.display()), e))?;
+            Ok(Box::new(file))
+        }
+        None => Ok(Box::new(io::stdout().lock())),
+    }
+}
+
+/// Creates or truncates a file that only the current user may read and write.
+pub fn create_private_file(path: &Path) -> io::Result<File> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+        options.mode(0o600);
+        let file = options.open(path)?;
+        // The mode above only applies to newly created files
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        Ok(file)
+    }
+
+    #[cfg(not(unix))]
+    options.open(path)
+}
@@ -0,0 +1,65 @@
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Runs `f` on every item using up to `jobs` threads and returns the results in input order.
+pub fn map<T, R, F>(items: &[T], jobs: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(items.len()));
+
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.min(items.len()) {
+            scope.spawn(|| {
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(item) = items.get(index) else {
+                        break;
+                    };
+                    let result = f(item);
+                    results.lock().unwrap().push((index, result));
+                }
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_unstable_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Runs `f` on the items using up to `jobs` threads until it returns `Some` for one of them.
+///
+/// Items still queued are skipped once a result is found; if several match, any one is returned.
+pub fn find_any<T, R, F>(items: &[T], jobs: usize, f: F) -> Option<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> Option<R> + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let done = AtomicBool::new(false);
+    let found = Mutex::new(None);
+
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.min(items.len()) {
+            scope.spawn(|| {
+                while !done.load(Ordering::Relaxed) {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(item) = items.get(index) else {
+                        break;
+                    };
+                    if let Some(result) = f(item) {
+                        *found.lock().unwrap() = Some(result);
+                        done.store(true, Ordering::Relaxed);
+                    }
+                }
+            });
+        }
+    });
+
+    found.into_inner().unwrap()
+}
@@ -0,0 +1,45 @@
+use crate::Settings;
+use argon2::Algorithm;
+use clap::ValueEnum;
+
+/// Published parameter recommendations, which explicit flags can still override.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Preset {
+    /// RFC 9106 first recommendation: Argon2id, 2 GiB, t=1, p=4
+    #[value(name = "rfc9106-high")]
+    Rfc9106High,
+    /// RFC 9106 second recommendation for memory-constrained settings: Argon2id, 64 MiB, t=3, p=4
+    #[value(name = "rfc9106-low")]
+    Rfc9106Low,
+    /// OWASP Password Storage Cheat Sheet: Argon2id, 19 MiB, t=2, p=1
+    #[value(name = "owasp")]
+    Owasp,
+}
+
+impl Preset {
+    pub fn settings(self) -> Settings {
+        match self {
+            Preset::Rfc9106High => Settings {
+                algorithm: Algorithm::Argon2id,
+                memory_kib: 2 * 1024 * 1024,
+                iterations: 1,
+                parallelism: 4,
+                hash_length: 32,
+            },
+            Preset::Rfc9106Low => Settings {
+                algorithm: Algorithm::Argon2id,
+                memory_kib: 64 * 1024,
+                iterations: 3,
+                parallelism: 4,
+                hash_length: 32,
+            },
+            Preset::Owasp => Settings {
+                algorithm: Algorithm::Argon2id,
+                memory_kib: 19 * 1024,
+                iterations: 2,
+                parallelism: 1,
+                hash_length: 32,
+            },
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use std::io::{self, IsTerminal, Write};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+const FRAMES: [char; 4] = ['|', '/', '-', '\\'];
+
+/// Draws a spinner with the elapsed time on stderr until dropped.
+///
+/// Argon2 runs all passes in one call, so there is no finer progress to report. Nothing is drawn
+/// when stderr is not a terminal, where the redrawn line would only clutter a log.
+pub struct Spinner {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Spinner {
+    pub fn start(message: &'static str) -> Self {
+        if !io::stderr().is_terminal() {
+            return Spinner { stop: None, thread: None };
+        }
+
+        let (stop, stopped) = mpsc::channel();
+        let thread = std::thread::spawn(move || {
+            let start = Instant::now();
+            let mut width = 0;
+            for frame in FRAMES.iter().cycle() {
+                let line = format!("{} {} {:.1}s", frame, message, start.elapsed().as_secs_f64());
+                width = line.len();
+                eprint!("\r{}", line);
+                let _ = io::stderr().flush();
+
+                match stopped.recv_timeout(Duration::from_millis(100)) {
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    _ => break,
+                }
+            }
+            // Leave the line empty for whatever is printed next
+            eprint!("\r{:width$}\r", "");
+        });
+
+        Spinner { stop: Some(stop), thread: Some(thread) }
+    }
+}
+
+impl Drop for Spinner {
+    fn drop(&mut self) {
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
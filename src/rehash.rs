@@ -0,0 +1,48 @@
+use crate::Settings;
+use crate::error::Error;
+use crate::inspect::Encoded;
+use argon2::Version;
+use argon2::password_hash::PasswordHash;
+
+/// Lists every way the encoded hash falls short of the target settings.
+///
+/// Memory, iterations, parallelism and hash length are treated as minimums,
+/// while algorithm and version have to match exactly.
+pub fn shortcomings(hash: &str, target: &Settings) -> Result<Vec<String>, Error> {
+    let hash = PasswordHash::new(hash)
+        .map_err(|e| Error::Params(format!("Invalid encoded hash: {}", e)))?;
+    let encoded = Encoded::parse(&hash)?;
+    let params = &encoded.params;
+    let hash_length = hash.hash.map_or(0, |h| h.len());
+
+    let mut reasons = Vec::new();
+    if encoded.algorithm != target.algorithm {
+        reasons.push(format!("Type:           {:?}, expected {:?}", encoded.algorithm, target.algorithm));
+    }
+    if encoded.version != Version::V0x13 {
+        reasons.push(format!("Version:        {:x}, expected 13", encoded.version as u32));
+    }
+    if params.m_cost() < target.memory_kib {
+        reasons.push(format!("Memory:         {} KiB, expected at least {} KiB", params.m_cost(), target.memory_kib));
+    }
+    if params.t_cost() < target.iterations {
+        reasons.push(format!("Iterations:     {}, expected at least {}", params.t_cost(), target.iterations));
+    }
+    if params.p_cost() < target.parallelism {
+        reasons.push(format!("Parallelism:    {}, expected at least {}", params.p_cost(), target.parallelism));
+    }
+    if hash_length < target.hash_length as usize {
+        reasons.push(format!("Hash length:    {} bytes, expected at least {} bytes", hash_length, target.hash_length));
+    }
+
+    Ok(reasons)
+}
+
+/// Prints why the hash should be upgraded and returns whether it already meets the target.
+pub fn run(hash: &str, target: &Settings) -> Result<bool, Error> {
+    let reasons = shortcomings(hash, target)?;
+    for reason in &reasons {
+        println!("{}", reason);
+    }
+    Ok(reasons.is_empty())
+}
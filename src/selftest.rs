@@ -0,0 +1,55 @@
+use crate::error::{self, Error};
+use argon2::{Algorithm, Argon2, AssociatedData, ParamsBuilder, Version};
+use std::process::ExitCode;
+
+/// Known-answer tags from the reference implementation's test vectors, RFC 9106 section 5 for v0x13.
+///
+/// All use a password of 32 bytes 0x01, a salt of 16 bytes 0x02, a secret of 8 bytes 0x03,
+/// associated data of 12 bytes 0x04, 32 KiB of memory, 3 iterations and 4 lanes.
+const VECTORS: [(Algorithm, Version, &str); 6] = [
+    (Algorithm::Argon2d, Version::V0x10, "96a9d4e5a1734092c85e29f410a45914a5dd1f5cbf08b2670da68a0285abf32b"),
+    (Algorithm::Argon2i, Version::V0x10, "87aeedd6517ab830cd9765cd8231abb2e647a5dee08f7c05e02fcb763335d0fd"),
+    (Algorithm::Argon2id, Version::V0x10, "b64615f07789b66b645b67ee9ed3b377ae350b6bfcbb0fc95141ea8f322613c0"),
+    (Algorithm::Argon2d, Version::V0x13, "512b391b6f1162975371d30919734294f868e3be3984f3c1a13a4db9fabe4acb"),
+    (Algorithm::Argon2i, Version::V0x13, "c814d9d1dc7f37aa13f0d77f2494bda1c8de6b016dd388d29952a4c4672b6ce8"),
+    (Algorithm::Argon2id, Version::V0x13, "0d640df58d78766c08c037a34a8b53c9d01ef0452d75b65eb52520e96b01e659"),
+];
+
+/// Hashes every test vector and compares the tags, exiting with 1 if any differs.
+pub fn run() -> Result<ExitCode, Error> {
+    let mut failed = 0;
+    for (algorithm, version, expected) in VECTORS {
+        let tag = hash(algorithm, version)?;
+        let name = format!("{:?} v0x{:x}", algorithm, u32::from(version));
+        if hex::encode(tag) == expected {
+            println!("{:<16}ok", name);
+        } else {
+            println!("{:<16}FAILED", name);
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        eprintln!("{} of {} test vectors failed", failed, VECTORS.len());
+        Ok(ExitCode::from(error::exit::MISMATCH))
+    } else {
+        println!("All {} test vectors passed", VECTORS.len());
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+fn hash(algorithm: Algorithm, version: Version) -> Result<[u8; 32], Error> {
+    let ad = AssociatedData::new(&[0x04; 12])
+        .map_err(|e| Error::Params(format!("Invalid associated data: {}", e)))?;
+    let params = ParamsBuilder::new()
+        .m_cost(32)
+        .t_cost(3)
+        .p_cost(4)
+        .data(ad)
+        .build()?;
+
+    let argon2 = Argon2::new_with_secret(&[0x03; 8], algorithm, version, params)?;
+    let mut tag = [0u8; 32];
+    argon2.hash_password_into(&[0x01; 32], &[0x02; 16], &mut tag)?;
+    Ok(tag)
+}
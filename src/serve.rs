@@ -0,0 +1,166 @@
+use crate::error::Error;
+use crate::memory::{self, Limit};
+use crate::{Args, Settings, build_argon2, build_verifier, check_salt, config, encode_salt, get_associated_data, get_secret};
+use argon2::password_hash::PasswordHash;
+use argon2::{Argon2, PasswordHasher, PasswordVerifier};
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, Write};
+use zeroize::{Zeroize, Zeroizing};
+
+/// One line of input:
+///
+/// ```json
+/// {"op":"hash","password":"hunter2","params":{"variant":"argon2id","memory_kib":19456}}
+/// {"op":"verify","password":"hunter2","hash":"$argon2id$v=19$..."}
+/// ```
+#[derive(Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase", deny_unknown_fields)]
+enum Request {
+    Hash {
+        password: String,
+        /// Taken as is like the positional salt, a random one is generated if missing
+        #[serde(default)]
+        salt: Option<String>,
+        #[serde(default)]
+        params: Params,
+    },
+    Verify {
+        password: String,
+        hash: String,
+    },
+}
+
+impl Drop for Request {
+    fn drop(&mut self) {
+        match self {
+            Request::Hash { password, .. } | Request::Verify { password, .. } => password.zeroize(),
+        }
+    }
+}
+
+/// Overrides for the parameters selected on the command line.
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Params {
+    variant: Option<config::Variant>,
+    memory_kib: Option<u32>,
+    iterations: Option<u32>,
+    parallelism: Option<u32>,
+    hash_length: Option<u32>,
+}
+
+/// One line of output, `{"ok":true,"hash":...}`, `{"ok":true,"match":false}` or `{"ok":false,"error":...}`.
+#[derive(Serialize)]
+struct Response {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hash: Option<String>,
+    #[serde(rename = "match", skip_serializing_if = "Option::is_none")]
+    matches: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl Drop for Response {
+    fn drop(&mut self) {
+        if let Some(hash) = &mut self.hash {
+            hash.zeroize();
+        }
+    }
+}
+
+/// Answers JSON requests read line by line from stdin until it is closed.
+///
+/// Responses are written in the order of the requests and flushed one at a time, so a caller can
+/// keep the process running and wait for one line per request.
+pub fn run(settings: &Settings, args: &Args) -> Result<(), Error> {
+    let secret = get_secret(args)?;
+    let associated_data = get_associated_data(args)?;
+    let secret = secret.as_deref().map(Vec::as_slice);
+    let limit = memory::limit(args)?;
+
+    let verifier = build_verifier(secret)?;
+
+    let mut stdin = io::stdin().lock();
+    let mut stdout = io::stdout().lock();
+    // Read as bytes so a line that is not UTF-8 gets an error response instead of ending the session
+    let mut line = Zeroizing::new(Vec::new());
+    loop {
+        line.clear();
+        let read = stdin.read_until(b'\n', &mut line)
+            .map_err(|e| Error::Io("Error reading input".to_string(), e))?;
+        if read == 0 {
+            return Ok(());
+        }
+        if line.trim_ascii().is_empty() {
+            continue;
+        }
+
+        let result = serde_json::from_slice(&line)
+            .map_err(|e| Error::Params(format!("Invalid request: {}", e)))
+            .and_then(|request| match &request {
+                Request::Hash { password, salt, params } => {
+                    hash(password, salt.as_deref(), params, settings, limit.as_ref(), secret, associated_data.as_deref())
+                }
+                Request::Verify { password, hash } => verify(&verifier, limit.as_ref(), password, hash),
+            });
+
+        let response = match result {
+            Ok(response) => response,
+            Err(e) => Response { ok: false, hash: None, matches: None, error: Some(e.to_string()) },
+        };
+        let json = serde_json::to_string(&response)
+            .map_err(|e| Error::Io("Cannot encode JSON".to_string(), e.into()))?;
+        writeln!(stdout, "{}", Zeroizing::new(json).as_str())?;
+        stdout.flush()?;
+    }
+}
+
+fn hash(
+    password: &str,
+    salt: Option<&str>,
+    params: &Params,
+    settings: &Settings,
+    limit: Option<&Limit>,
+    secret: Option<&[u8]>,
+    associated_data: Option<&[u8]>,
+) -> Result<Response, Error> {
+    let settings = config::Defaults {
+        variant: params.variant,
+        memory_kib: params.memory_kib,
+        iterations: params.iterations,
+        parallelism: params.parallelism,
+        hash_length: params.hash_length,
+        format: None,
+    }.apply(*settings);
+    memory::check(limit, settings.memory_kib, 1)?;
+
+    let salt = match salt {
+        Some(salt) => check_salt(salt.as_bytes().to_vec())?,
+        None => {
+            let mut salt = vec![0u8; 16];
+            getrandom::fill(&mut salt)
+                .map_err(|e| Error::Io("Cannot generate salt".to_string(), e.into()))?;
+            salt
+        }
+    };
+    let salt_string = encode_salt(&salt)?;
+
+    let argon2 = build_argon2(&settings, secret, associated_data)?;
+    let hash = argon2.hash_password(password.as_bytes(), salt_string.as_salt())?;
+
+    Ok(Response { ok: true, hash: Some(hash.to_string()), matches: None, error: None })
+}
+
+fn verify(verifier: &Argon2, limit: Option<&Limit>, password: &str, hash: &str) -> Result<Response, Error> {
+    let hash = PasswordHash::new(hash)
+        .map_err(|e| Error::Params(format!("Invalid hash: {}", e)))?;
+    memory::check(limit, argon2::Params::try_from(&hash)?.m_cost(), 1)?;
+    let matches = match verifier.verify_password(password.as_bytes(), &hash) {
+        Ok(()) => true,
+        Err(argon2::password_hash::Error::Password) => false,
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok(Response { ok: true, hash: None, matches: Some(matches), error: None })
+}
@@ -0,0 +1,102 @@
+use std::io::{self, BufRead, Write};
+use zeroize::Zeroizing;
+
+/// Prompts on stderr and reads one line from the terminal without echoing it.
+///
+/// Like `passwd`, the prompt stays out of stdout so that redirected output only holds the result.
+pub fn read_password(prompt: &str) -> io::Result<Zeroizing<Vec<u8>>> {
+    eprint!("{}", prompt);
+    io::stderr().flush()?;
+
+    // Reserve enough up front that growing the buffer does not leave copies behind
+    let mut input = Zeroizing::new(Vec::with_capacity(1024));
+    {
+        let _guard = EchoGuard::disable()?;
+        io::stdin().lock().read_until(b'\n', &mut input)?;
+    }
+
+    // The newline typed by the user was swallowed together with the echo
+    eprintln!();
+    Ok(input)
+}
+
+/// Turns off terminal echo on stdin until dropped.
+#[cfg(unix)]
+struct EchoGuard {
+    fd: std::os::fd::RawFd,
+    original: libc::termios,
+}
+
+#[cfg(unix)]
+impl EchoGuard {
+    fn disable() -> io::Result<Self> {
+        use std::os::fd::AsRawFd;
+
+        let fd = io::stdin().as_raw_fd();
+        let mut original = std::mem::MaybeUninit::<libc::termios>::uninit();
+        if unsafe { libc::tcgetattr(fd, original.as_mut_ptr()) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let original = unsafe { original.assume_init() };
+
+        let mut silent = original;
+        silent.c_lflag &= !libc::ECHO;
+        if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &silent) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self { fd, original })
+    }
+}
+
+#[cfg(unix)]
+impl Drop for EchoGuard {
+    fn drop(&mut self) {
+        unsafe { libc::tcsetattr(self.fd, libc::TCSANOW, &self.original) };
+    }
+}
+
+#[cfg(windows)]
+mod console {
+    use std::ffi::c_void;
+
+    pub const ENABLE_ECHO_INPUT: u32 = 0x0004;
+
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        pub fn GetConsoleMode(handle: *mut c_void, mode: *mut u32) -> i32;
+        pub fn SetConsoleMode(handle: *mut c_void, mode: u32) -> i32;
+    }
+}
+
+/// Turns off console echo on stdin until dropped.
+#[cfg(windows)]
+struct EchoGuard {
+    handle: std::os::windows::io::RawHandle,
+    original: u32,
+}
+
+#[cfg(windows)]
+impl EchoGuard {
+    fn disable() -> io::Result<Self> {
+        use std::os::windows::io::AsRawHandle;
+
+        let handle = io::stdin().as_raw_handle();
+        let mut original = 0;
+        if unsafe { console::GetConsoleMode(handle, &mut original) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if unsafe { console::SetConsoleMode(handle, original & !console::ENABLE_ECHO_INPUT) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self { handle, original })
+    }
+}
+
+#[cfg(windows)]
+impl Drop for EchoGuard {
+    fn drop(&mut self) {
+        unsafe { console::SetConsoleMode(self.handle, self.original) };
+    }
+}
@@ -0,0 +1,438 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+const RUST_BINARY: &str = env!("CARGO_BIN_EXE_argon2-cli");
+
+fn run(args: &[&str], stdin: &[u8]) -> Output {
+    let mut child = Command::new(RUST_BINARY)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to spawn binary");
+
+    // The binary may exit on an argument error before it reads stdin
+    if let Err(e) = child.stdin.take().unwrap().write_all(stdin) {
+        assert_eq!(e.kind(), std::io::ErrorKind::BrokenPipe, "Failed to write to stdin: {}", e);
+    }
+    child.wait_with_output().expect("Failed to wait")
+}
+
+#[test]
+fn test_rfc9106_secret_and_associated_data() {
+    // RFC 9106, section 5.3: Argon2id test vector
+    let secret_file = std::env::temp_dir().join("argon2-cli-rfc9106-secret");
+    std::fs::write(&secret_file, [0x03; 8]).unwrap();
+
+    let salt = "\u{2}".repeat(16);
+    let output = run(
+        &[
+            &salt, "-id", "-t", "3", "-k", "32", "-p", "4", "-r",
+            "--secret-file", secret_file.to_str().unwrap(),
+            "--ad", "040404040404040404040404",
+        ],
+        &[0x01; 32],
+    );
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(
+        hex::encode(output.stdout),
+        "0d640df58d78766c08c037a34a8b53c9d01ef0452d75b65eb52520e96b01e659"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_secret_env_is_byte_exact() {
+    use std::os::unix::ffi::OsStrExt;
+
+    let secret = b"pep\xffper";
+    let secret_file = std::env::temp_dir().join("argon2-cli-binary-secret");
+    std::fs::write(&secret_file, secret).unwrap();
+
+    let from_file = run(&["somesalt", "-r", "--secret-file", secret_file.to_str().unwrap()], b"password");
+    let from_env = Command::new(RUST_BINARY)
+        .args(["somesalt", "-r", "--secret-env", "ARGON2_TEST_SECRET"])
+        .env("ARGON2_TEST_SECRET", std::ffi::OsStr::from_bytes(secret))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            child.stdin.take().unwrap().write_all(b"password")?;
+            child.wait_with_output()
+        })
+        .expect("Failed to run binary");
+
+    assert!(from_env.status.success());
+    assert_eq!(from_file.stdout, from_env.stdout);
+}
+
+#[test]
+fn test_gen_salt() {
+    let salt = |output: Output| {
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        stdout.lines().find_map(|line| line.strip_prefix("Salt:")).unwrap().trim().to_string()
+    };
+
+    let first = salt(run(&["--gen-salt", "-k", "64", "-t", "1"], b"password"));
+    let second = salt(run(&["--gen-salt", "-k", "64", "-t", "1"], b"password"));
+    assert_eq!(hex::decode(&first).unwrap().len(), 16);
+    assert_ne!(first, second);
+
+    assert_eq!(run(&["--gen-salt", "4"], b"password").status.code(), Some(3));
+    assert_eq!(run(&["--gen-salt", "99999999999999"], b"password").status.code(), Some(2));
+
+    let too_long = run(&["--gen-salt", "49", "-e"], b"password");
+    assert_eq!(too_long.status.code(), Some(2));
+    assert!(String::from_utf8(too_long.stderr).unwrap().contains("holds at most 48 bytes"));
+}
+
+#[test]
+fn test_salt_formats_round_trip() {
+    let salt_file = std::env::temp_dir().join("argon2-cli-salt-file");
+    std::fs::write(&salt_file, "736f6d6573616c74\n").unwrap();
+
+    let raw = run(&["somesalt", "-e"], b"password");
+    assert!(raw.status.success());
+    for args in [
+        &["736f6d6573616c74", "--salt-format", "hex"][..],
+        &["c29tZXNhbHQ", "--salt-format", "b64"],
+        &["c29tZXNhbHQ=", "--salt-format", "b64"],
+        &["--salt-file", salt_file.to_str().unwrap(), "--salt-format", "hex"],
+    ] {
+        let encoded = run(&[args, &["-e"]].concat(), b"password");
+        assert_eq!(encoded.stdout, raw.stdout, "{:?}", args);
+    }
+}
+
+#[test]
+fn test_kdf_matches_raw_hash() {
+    let raw = run(&["somesalt", "-id", "-r"], b"password");
+    let kdf = run(&["somesalt", "-id", "--kdf", "--raw-encoding", "hex"], b"password");
+
+    assert!(raw.status.success() && kdf.status.success());
+    assert_eq!(String::from_utf8(kdf.stdout).unwrap().trim_end(), hex::encode(raw.stdout));
+
+    // A derived key has no PHC string, so the salt may be longer than an encoded hash holds
+    let salt_file = std::env::temp_dir().join("argon2-cli-long-salt");
+    std::fs::write(&salt_file, [0x02; 60]).unwrap();
+    let long = run(&["--salt-file", salt_file.to_str().unwrap(), "--kdf", "--raw-encoding", "hex"], b"password");
+    assert!(long.status.success(), "{}", String::from_utf8_lossy(&long.stderr));
+    assert_eq!(String::from_utf8(long.stdout).unwrap().trim_end().len(), 64);
+
+    let generated = run(&["--gen-salt", "64", "--kdf"], b"password");
+    assert!(generated.status.success(), "{}", String::from_utf8_lossy(&generated.stderr));
+}
+
+#[test]
+fn test_measure_reports_every_run() {
+    let output = run(&["somesalt", "-t", "1", "-k", "64", "--measure", "3"], b"password");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.lines().any(|line| line.ends_with("stddev over 3 runs")));
+
+    // These views have no room for the timing, so it is refused instead of dropped
+    for flag in ["-e", "-r", "-q"] {
+        assert_eq!(run(&["somesalt", "--measure", "3", flag], b"password").status.code(), Some(2), "{}", flag);
+    }
+}
+
+#[test]
+fn test_password_is_byte_exact() {
+    let plain = run(&["somesalt", "-r"], b"password");
+    let newline = run(&["somesalt", "-r"], b"password\n");
+    let stripped = run(&["somesalt", "-r", "--strip-newline"], b"password\r\n");
+    assert_ne!(plain.stdout, newline.stdout);
+    assert_eq!(plain.stdout, stripped.stdout);
+
+    let binary = run(&["somesalt", "-r"], b"pass\xffword");
+    assert!(binary.status.success());
+    assert_ne!(plain.stdout, binary.stdout);
+
+    // Long enough that the input buffer has to grow a few times
+    let long: String = (0..5000).map(|i| (b'a' + (i % 26) as u8) as char).collect();
+    let from_stdin = run(&["somesalt", "-r"], long.as_bytes());
+    let from_env = Command::new(RUST_BINARY)
+        .args(["somesalt", "-r", "--password-env", "ARGON2_TEST_PASSWORD"])
+        .env("ARGON2_TEST_PASSWORD", &long)
+        .output()
+        .expect("Failed to run binary");
+    assert!(from_stdin.status.success() && from_env.status.success());
+    assert_eq!(from_stdin.stdout, from_env.stdout);
+}
+
+#[test]
+fn test_check() {
+    let alice = run(&["somesalt", "-e", "-t", "1", "-k", "64"], b"alice");
+    let bob = run(&["othersalt", "-id", "-e", "-t", "1", "-k", "64"], b"bob");
+    let list = std::env::temp_dir().join("argon2-cli-check-list");
+    let hash = |output: Output| String::from_utf8(output.stdout).unwrap().trim_end().to_string();
+    std::fs::write(&list, format!("{} alice\n{} bob\n", hash(alice), hash(bob))).unwrap();
+    let list = list.to_str().unwrap();
+
+    let ok = run(&["--check", list], b"alice\nbob\n");
+    assert_eq!(ok.status.code(), Some(0));
+    assert_eq!(String::from_utf8(ok.stdout).unwrap(), "alice: OK\nbob: OK\n");
+
+    let failed = run(&["--check", list, "--jobs", "2"], b"alice\nmallory\n");
+    assert_eq!(failed.status.code(), Some(1));
+    assert_eq!(String::from_utf8(failed.stdout).unwrap(), "alice: OK\nbob: FAILED\n");
+}
+
+#[test]
+fn test_progress_keeps_output_clean() {
+    let plain = run(&["somesalt", "-e"], b"password");
+    let progress = run(&["somesalt", "-e", "--progress"], b"password");
+
+    assert_eq!(plain.stdout, progress.stdout);
+    assert!(progress.stderr.is_empty());
+}
+
+#[test]
+fn test_raw_encoding() {
+    let raw = run(&["somesalt", "-r"], b"password");
+    let hex = run(&["somesalt", "-r", "--raw-encoding", "hex"], b"password");
+    let b64url = run(&["somesalt", "-q", "--raw-encoding", "b64url"], b"password");
+
+    assert_eq!(String::from_utf8(hex.stdout).unwrap(), format!("{}\n", hex::encode(&raw.stdout)));
+    let b64url = String::from_utf8(b64url.stdout).unwrap();
+    assert!(b64url.starts_with("Hash:           iWh06vD8Fy27wf9npn6FXWiCX4K6pW6Ue1Bnzz07Z8A\n"));
+
+    // Raw bytes only fit -r and --kdf, and batch output is always encoded
+    assert_eq!(run(&["somesalt", "--raw-encoding", "raw"], b"password").status.code(), Some(2));
+    assert_eq!(run(&["somesalt", "-q", "--raw-encoding", "raw"], b"password").status.code(), Some(2));
+    assert_eq!(run(&["somesalt", "--batch", "--raw-encoding", "b64"], b"password").status.code(), Some(2));
+}
+
+#[test]
+fn test_max_memory() {
+    let within = run(&["somesalt", "-k", "4096", "--max-memory", "4096"], b"password");
+    assert!(within.status.success());
+
+    let exceeded = run(&["somesalt", "--batch", "--jobs", "2", "-k", "4096", "--max-memory", "4096"], b"a\nb\n");
+    assert_eq!(exceeded.status.code(), Some(2));
+    assert!(String::from_utf8(exceeded.stderr).unwrap().contains("limit of 4096 KiB from --max-memory"));
+}
+
+#[test]
+fn test_crack() {
+    let hash = run(&["somesalt", "-id", "-e", "-t", "1", "-k", "64"], b"hunter2");
+    let hash = String::from_utf8(hash.stdout).unwrap();
+    let wordlist = std::env::temp_dir().join("argon2-cli-crack-wordlist");
+
+    std::fs::write(&wordlist, "password\nletmein\nhunter2\nqwerty\n").unwrap();
+    let found = run(&["crack", "--hash", hash.trim_end(), "--wordlist", wordlist.to_str().unwrap(), "--jobs", "2"], b"");
+    assert_eq!(found.status.code(), Some(0));
+    assert_eq!(found.stdout, b"hunter2\n");
+
+    std::fs::write(&wordlist, "password\nletmein\n").unwrap();
+    let missed = run(&["crack", "--hash", hash.trim_end(), "--wordlist", wordlist.to_str().unwrap()], b"");
+    assert_eq!(missed.status.code(), Some(1));
+    assert!(missed.stdout.is_empty());
+
+    // Without a version field the hash is from the original 0x10 release, like inspect reports
+    let legacy = "$argon2id$m=64,t=1,p=1$c29tZXNhbHQ$mrD4ebGXAm/iwPGB1eSGGzXQjxOzbrl31yckg2GhPv8";
+    std::fs::write(&wordlist, "letmein
+hunter2
+").unwrap();
+    let found = run(&["crack", "--hash", legacy, "--wordlist", wordlist.to_str().unwrap()], b"");
+    assert_eq!(found.status.code(), Some(0));
+    assert_eq!(found.stdout, b"hunter2\n");
+}
+
+#[test]
+fn test_self_test() {
+    let output = run(&["--self-test"], b"");
+
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().filter(|line| line.ends_with(" ok")).count(), 6);
+}
+
+#[test]
+fn test_generate_man_and_completions() {
+    let man = run(&["--generate-man"], b"");
+    assert_eq!(man.status.code(), Some(0));
+    assert!(String::from_utf8(man.stdout).unwrap().lines().any(|line| line.starts_with(".TH ")));
+
+    let completions = run(&["--generate-completions", "bash"], b"");
+    assert_eq!(completions.status.code(), Some(0));
+    assert!(!completions.stdout.is_empty());
+}
+
+#[test]
+fn test_inspect() {
+    let hash = "$argon2i$v=19$m=4096,t=3,p=1$c29tZXNhbHQ$0Qnq2w7zJ8I3xz12F+3WnEApJIWtZqXFbLYeggWl+J0";
+    let output = run(&["inspect", hash], b"");
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "Type:           Argon2i\n\
+         Version:        13\n\
+         Iterations:     3\n\
+         Memory:         4096 KiB\n\
+         Parallelism:    1\n\
+         Salt:           736f6d6573616c74\n\
+         Hash length:    32 bytes\n"
+    );
+
+    assert_eq!(run(&["inspect", "$argon2i$garbage"], b"").status.code(), Some(2));
+}
+
+#[test]
+fn test_preset() {
+    let encoded = |args: &[&str]| {
+        let output = run(&[&["somesalt", "-e"], args].concat(), b"password");
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert!(encoded(&["--preset", "owasp"]).starts_with("$argon2id$v=19$m=19456,t=2,p=1$"));
+    assert!(encoded(&["--preset", "owasp", "-t", "1"]).starts_with("$argon2id$v=19$m=19456,t=1,p=1$"));
+}
+
+#[test]
+fn test_output_file_and_quiet() {
+    let path = std::env::temp_dir().join("argon2-cli-output-file");
+    let _ = std::fs::remove_file(&path);
+
+    let output = run(&["somesalt", "-e", "-o", path.to_str().unwrap()], b"password");
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "$argon2i$v=19$m=4096,t=3,p=1$c29tZXNhbHQ$iWh06vD8Fy27wf9npn6FXWiCX4K6pW6Ue1Bnzz07Z8A\n"
+    );
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+
+    let quiet = run(&["somesalt", "-q"], b"password");
+    let lines: Vec<String> = String::from_utf8(quiet.stdout).unwrap().lines().map(str::to_string).collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("Hash:") && lines[1].starts_with("Encoded:"));
+}
+
+#[test]
+fn test_serve() {
+    let expected = run(&["somesalt", "-e", "-t", "1", "-k", "64"], b"hunter2");
+    let expected = String::from_utf8(expected.stdout).unwrap();
+    let expected = expected.trim_end();
+
+    let mut requests = Vec::new();
+    requests.extend_from_slice(
+        br#"{"op":"hash","password":"hunter2","salt":"somesalt","params":{"memory_kib":64,"iterations":1}}"#,
+    );
+    requests.extend_from_slice(format!("\n{{\"op\":\"verify\",\"password\":\"hunter2\",\"hash\":\"{}\"}}\n", expected).as_bytes());
+    requests.extend_from_slice(format!("{{\"op\":\"verify\",\"password\":\"letmein\",\"hash\":\"{}\"}}\n", expected).as_bytes());
+    requests.extend_from_slice(b"not json\n\n\xff\xfe\n{\"op\":\"delete\"}\n");
+
+    let output = run(&["--serve"], &requests);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let responses: Vec<&str> = stdout.lines().collect();
+
+    // One response per request in order, blank lines are skipped and errors keep the session going
+    assert_eq!(responses.len(), 6, "{}", stdout);
+    assert_eq!(responses[0], format!("{{\"ok\":true,\"hash\":\"{}\"}}", expected));
+    assert_eq!(responses[1], r#"{"ok":true,"match":true}"#);
+    assert_eq!(responses[2], r#"{"ok":true,"match":false}"#);
+    for response in &responses[3..] {
+        assert!(response.starts_with(r#"{"ok":false,"error":"Invalid request: "#), "{}", response);
+    }
+}
+
+#[test]
+fn test_config_profiles() {
+    let path = std::env::temp_dir().join("argon2-cli-config-profiles.toml");
+    std::fs::write(
+        &path,
+        "[defaults]\nvariant = \"argon2id\"\nmemory_kib = 64\n\n[profiles.fast]\niterations = 1\nformat = \"encoded\"\n",
+    )
+    .unwrap();
+    let config = path.to_str().unwrap();
+
+    let defaults = run(&["somesalt", "--config", config, "-e"], b"password");
+    assert!(defaults.status.success());
+    assert!(String::from_utf8(defaults.stdout).unwrap().starts_with("$argon2id$v=19$m=64,t=3,p=1$"));
+
+    // The profile overrides the iterations and the format, the rest falls back to [defaults]
+    let fast = run(&["somesalt", "--config", config, "--profile", "fast"], b"password");
+    assert!(fast.status.success());
+    assert!(String::from_utf8(fast.stdout).unwrap().starts_with("$argon2id$v=19$m=64,t=1,p=1$"));
+
+    let flag = run(&["somesalt", "--config", config, "--profile", "fast", "-t", "2"], b"password");
+    assert!(String::from_utf8(flag.stdout).unwrap().starts_with("$argon2id$v=19$m=64,t=2,p=1$"));
+
+    let unknown = run(&["somesalt", "--config", config, "--profile", "slow"], b"password");
+    assert_eq!(unknown.status.code(), Some(2));
+    assert!(String::from_utf8(unknown.stderr).unwrap().contains("Unknown profile: slow"));
+}
+
+#[test]
+fn test_config_format_yields_to_flags() {
+    let path = std::env::temp_dir().join("argon2-cli-config-format.toml");
+    std::fs::write(&path, "[defaults]\nformat = \"encoded\"\n").unwrap();
+    let config = path.to_str().unwrap();
+
+    let output = |args: &[&str]| {
+        let output = run(&[&["somesalt", "--config", config], args].concat(), b"password");
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert!(output(&[]).starts_with("$argon2i$v=19$m=4096,t=3,p=1$c29tZXNhbHQ$"));
+    assert!(output(&["--json"]).starts_with('{'));
+    assert!(output(&["--measure", "2"]).contains("over 2 runs"));
+    assert!(output(&["--raw-encoding", "hex"]).starts_with("Type:"));
+}
+
+#[test]
+fn test_needs_rehash() {
+    let hash = "$argon2i$v=19$m=4096,t=3,p=1$c29tZXNhbHQ$0Qnq2w7zJ8I3xz12F+3WnEApJIWtZqXFbLYeggWl+J0";
+
+    let current = run(&["--needs-rehash", hash], b"");
+    assert_eq!(current.status.code(), Some(0));
+
+    let upgraded = run(&["--needs-rehash", hash, "-id", "-m", "16"], b"");
+    assert_eq!(upgraded.status.code(), Some(1));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_password_fd_must_be_open() {
+    let output = run(&["somesalt", "--password-fd", "99"], b"");
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8(output.stderr).unwrap().contains("File descriptor 99 is not open"));
+}
+
+#[test]
+fn test_exit_codes() {
+    assert_eq!(run(&["short"], b"password").status.code(), Some(3));
+    assert_eq!(run(&["somesalt", "-t", "0"], b"password").status.code(), Some(2));
+    assert_eq!(run(&["somesalt", "-m", "32"], b"password").status.code(), Some(2));
+    assert_eq!(run(&["somesalt", "--password-file", "/nonexistent"], b"").status.code(), Some(4));
+}
+
+#[test]
+fn test_batch_preserves_order() {
+    let passwords = ["first", "second", "third", "fourth"];
+    assert_eq!(run(&["somesalt", "--jobs", "3", "-e"], b"password").status.code(), Some(2));
+
+    let batch = run(&["somesalt", "--batch", "--jobs", "3"], passwords.join("\n").as_bytes());
+    assert!(batch.status.success());
+
+    let batch = String::from_utf8(batch.stdout).unwrap();
+    for (line, password) in batch.lines().zip(passwords) {
+        let single = run(&["somesalt", "-e"], password.as_bytes());
+        assert_eq!(line, String::from_utf8(single.stdout).unwrap().trim_end());
+    }
+    assert_eq!(batch.lines().count(), passwords.len());
+}